@@ -0,0 +1,88 @@
+/// Iterator adapter that yields `&str` slices no longer than `max_len` bytes, never splitting a
+/// UTF-8 codepoint, and preferring to break on the last newline within range so multi-line
+/// citations don't get cut mid-sentence.
+pub struct Chunks<'a> {
+    remaining: &'a str,
+    max_len: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+
+        if self.remaining.len() <= self.max_len {
+            let chunk = self.remaining;
+            self.remaining = "";
+            return Some(chunk);
+        }
+
+        let mut split_at = self.max_len;
+        while split_at > 0 && !self.remaining.is_char_boundary(split_at) {
+            split_at -= 1;
+        }
+
+        if split_at == 0 {
+            // `max_len` is smaller than the first character here; emit it whole rather than
+            // never making progress.
+            let first_char_len = self.remaining.chars().next().map_or(1, char::len_utf8);
+            split_at = first_char_len;
+        } else if let Some(newline_at) = self.remaining[..split_at].rfind('\n') {
+            split_at = newline_at + 1;
+        }
+
+        let (chunk, rest) = self.remaining.split_at(split_at);
+        self.remaining = rest;
+        Some(chunk)
+    }
+}
+
+pub trait StrChunksExt {
+    /// Splits `self` into a sequence of slices no longer than `max_len` bytes each.
+    fn chunks(&self, max_len: usize) -> Chunks;
+}
+
+impl StrChunksExt for str {
+    fn chunks(&self, max_len: usize) -> Chunks {
+        Chunks {
+            remaining: self,
+            max_len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_within_limit() {
+        let chunks: Vec<&str> = "hello world".chunks(5).collect();
+        assert_eq!(chunks, vec!["hello", " worl", "d"]);
+    }
+
+    #[test]
+    fn never_splits_a_multi_byte_codepoint() {
+        // "é" is 2 bytes; a limit of 1 would split it if we didn't back off to a char boundary.
+        let chunks: Vec<&str> = "aébc".chunks(2).collect();
+        for chunk in &chunks {
+            assert!(chunk.is_char_boundary(0) && chunk.is_char_boundary(chunk.len()));
+        }
+        assert_eq!(chunks.concat(), "aébc");
+    }
+
+    #[test]
+    fn prefers_breaking_on_newline() {
+        let chunks: Vec<&str> = "short\nline that is too long".chunks(10).collect();
+        assert_eq!(chunks[0], "short\n");
+    }
+
+    #[test]
+    fn zero_max_len_terminates() {
+        let chunks: Vec<&str> = "abc".chunks(0).collect();
+        assert_eq!(chunks, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,187 @@
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, RoleId};
+use serenity::model::interactions::{
+    ApplicationCommand, ApplicationCommandOptionType, Interaction, InteractionResponseType,
+};
+
+use std::fs;
+
+use crate::logging::*;
+use crate::{Config, Tag, CONFIG_FILE_LOCATION};
+
+/// Registers the `/tag-help`, `/tag-add`, `/tag-remove` and `/tag-list` slash commands globally.
+///
+/// Called once from `ready`. Discord caches global command registrations, so re-running this
+/// on every startup is a no-op in practice but keeps the command list in sync with this list.
+pub fn register_commands(http: &Http) -> Result<()> {
+    ApplicationCommand::create_global_application_command(http, |c| {
+        c.name("tag-help").description("Explain how tagging works")
+    })?;
+
+    ApplicationCommand::create_global_application_command(http, |c| {
+        c.name("tag-add")
+            .description("Add a new tag")
+            .create_option(|o| {
+                o.name("emoji")
+                    .description("Name of the emoji that triggers this tag")
+                    .kind(ApplicationCommandOptionType::String)
+                    .required(true)
+            })
+            .create_option(|o| {
+                o.name("channel")
+                    .description("Channel to repost tagged messages into")
+                    .kind(ApplicationCommandOptionType::Channel)
+                    .required(true)
+            })
+            .create_option(|o| {
+                o.name("keep")
+                    .description("Number of messages to keep the original post for")
+                    .kind(ApplicationCommandOptionType::Integer)
+                    .required(true)
+            })
+    })?;
+
+    ApplicationCommand::create_global_application_command(http, |c| {
+        c.name("tag-remove")
+            .description("Remove a tag")
+            .create_option(|o| {
+                o.name("emoji")
+                    .description("Name of the emoji for the tag to remove")
+                    .kind(ApplicationCommandOptionType::String)
+                    .required(true)
+            })
+    })?;
+
+    ApplicationCommand::create_global_application_command(http, |c| {
+        c.name("tag-list").description("List the currently configured tags")
+    })?;
+
+    Ok(())
+}
+
+/// Returns true if the interaction's invoking member holds `role`.
+fn is_console(interaction: &Interaction, role: u64) -> bool {
+    interaction
+        .member
+        .as_ref()
+        .map(|m| m.roles.contains(&RoleId(role)))
+        .unwrap_or(false)
+}
+
+/// Dispatches a slash command interaction, mutating `config.tags` and persisting the change to
+/// disk when a command changes the live configuration.
+pub fn handle_interaction(http: &Http, interaction: &Interaction, config: &mut Config) -> Result<()> {
+    let command_name = match &interaction.data {
+        Some(data) => data.name.clone(),
+        None => return Ok(()),
+    };
+
+    if !is_console(interaction, config.roles.console) {
+        return respond(http, interaction, "You don't have permission to do that.");
+    }
+
+    match command_name.as_str() {
+        "tag-help" => respond(
+            http,
+            interaction,
+            "How to tag a message: \n \n 1. React with the appropriate emoji. \n 2. Wait for me to move it \n 3. ??? \n 4. Profit!",
+        ),
+        "tag-add" => {
+            let options = &interaction.data.as_ref().unwrap().options;
+            let emoji_name = string_option(options, "emoji").unwrap_or_default();
+            let channel_target = channel_option(options, "channel").unwrap_or_default();
+            let keep = integer_option(options, "keep").unwrap_or(0);
+
+            if keep < 0 || keep > i64::from(u16::MAX) {
+                return respond(
+                    http,
+                    interaction,
+                    &format!("`keep` must be between 0 and {}.", u16::MAX),
+                );
+            }
+            let message_counter = keep as u16;
+
+            config.tags.push(Tag {
+                channel_target: ChannelId(channel_target),
+                emoji_name: emoji_name.clone(),
+                message_counter,
+                use_embeds: true,
+                expire_after: None,
+            });
+            write_config(config)?;
+
+            respond(http, interaction, &format!("Added tag for emoji `{}`.", emoji_name))
+        }
+        "tag-remove" => {
+            let options = &interaction.data.as_ref().unwrap().options;
+            let emoji_name = string_option(options, "emoji").unwrap_or_default();
+
+            let before = config.tags.len();
+            config.tags.retain(|t| t.emoji_name != emoji_name);
+            write_config(config)?;
+
+            if config.tags.len() < before {
+                respond(http, interaction, &format!("Removed tag for emoji `{}`.", emoji_name))
+            } else {
+                respond(http, interaction, &format!("No tag found for emoji `{}`.", emoji_name))
+            }
+        }
+        "tag-list" => {
+            let list = if config.tags.is_empty() {
+                "No tags configured.".to_string()
+            } else {
+                config
+                    .tags
+                    .iter()
+                    .map(|t| format!("`{}` -> <#{}> (keep {})", t.emoji_name, t.channel_target, t.message_counter))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+
+            respond(http, interaction, &list)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn respond(http: &Http, interaction: &Interaction, content: &str) -> Result<()> {
+    interaction.create_interaction_response(http, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| d.content(content))
+    })?;
+
+    Ok(())
+}
+
+fn string_option(options: &[serenity::model::interactions::ApplicationCommandInteractionDataOption], name: &str) -> Option<String> {
+    options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str().map(str::to_owned))
+}
+
+fn integer_option(options: &[serenity::model::interactions::ApplicationCommandInteractionDataOption], name: &str) -> Option<i64> {
+    options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_i64())
+}
+
+fn channel_option(options: &[serenity::model::interactions::ApplicationCommandInteractionDataOption], name: &str) -> Option<u64> {
+    options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse().ok())
+}
+
+/// Serializes `config` back to `CONFIG_FILE_LOCATION` so tag changes made from Discord survive restarts.
+fn write_config(config: &Config) -> Result<()> {
+    let serialized = toml::to_string_pretty(config)?;
+    fs::write(CONFIG_FILE_LOCATION, serialized)?;
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Duration, Utc};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::prelude::*;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use crate::logging::*;
+use crate::storage;
+use crate::{ChannelMapType, StorageType};
+
+/// How often the background task scans the channel map for expired tagged messages.
+const SCAN_INTERVAL: StdDuration = StdDuration::from_secs(30);
+
+/// Parses a short human-friendly duration such as `"30m"` or `"2h"`.
+///
+/// Supported suffixes: `s` (seconds), `m` (minutes), `h` (hours), `d` (days).
+pub fn parse_duration(input: &str) -> std::result::Result<Duration, String> {
+    let input = input.trim();
+    if input.len() < 2 || !input.is_char_boundary(input.len() - 1) {
+        return Err(format!("duration \"{}\" is missing a unit", input));
+    }
+
+    let (amount, unit) = input.split_at(input.len() - 1);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("duration \"{}\" has an invalid amount", input))?;
+
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => Err(format!("duration \"{}\" has an unknown unit \"{}\"", input, unit)),
+    }
+}
+
+/// Computes the absolute deadline for a tag's `expire_after`, if it has one. Invalid durations
+/// are logged and treated as no deadline, falling back to the message-counter path.
+pub fn deadline_for(expire_after: &Option<String>) -> Option<DateTime<Utc>> {
+    expire_after.as_ref().and_then(|raw| match parse_duration(raw) {
+        Ok(duration) => Some(Utc::now() + duration),
+        Err(e) => {
+            format!("Ignoring invalid expire_after \"{}\": {}", raw, e).log();
+            None
+        }
+    })
+}
+
+/// Spawns a background thread that periodically deletes tagged messages past their deadline,
+/// as an alternative to the message-counter deletion path in `Handler::message`.
+///
+/// The data lock is only held long enough to collect expired entries and, separately, to record
+/// their removal; the (potentially many, potentially slow) Discord API calls happen with the
+/// lock released so `message`/`reaction_add`/`interaction_create` aren't stalled by a scan.
+pub fn spawn_expiry_task(http: Http, data: Arc<RwLock<TypeMap>>) {
+    thread::spawn(move || loop {
+        thread::sleep(SCAN_INTERVAL);
+
+        let now = Utc::now();
+
+        let expired: Vec<(ChannelId, MessageId)> = {
+            let data = data.read();
+            let channel_map = data.get::<ChannelMapType>().unwrap();
+
+            channel_map
+                .iter()
+                .flat_map(|(channel, tagged_messages)| {
+                    tagged_messages
+                        .iter()
+                        .filter(move |message| message.deadline.map_or(false, |deadline| deadline <= now))
+                        .map(move |message| (*channel, message.message_id))
+                })
+                .collect()
+        };
+
+        if expired.is_empty() {
+            continue;
+        }
+
+        for (channel, message_id) in &expired {
+            match http.delete_message(u64::from(*channel), u64::from(*message_id)) {
+                Err(e) => BotError::Discord(e).log(),
+                Ok(_) => {}
+            }
+        }
+
+        let touched_channels: HashSet<ChannelId> = expired.iter().map(|(channel, _)| *channel).collect();
+
+        let mut data = data.write();
+        let db = data.get::<StorageType>().unwrap().clone();
+        let channel_map = data.get_mut::<ChannelMapType>().unwrap();
+
+        for channel in touched_channels {
+            if let Some(tagged_messages) = channel_map.get_mut(&channel) {
+                tagged_messages.retain(|message| message.deadline.map_or(true, |deadline| deadline > now));
+                storage::save_channel(&db, channel, tagged_messages).unwrap_gracefully();
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_supported_unit() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::seconds(30));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::days(1));
+    }
+
+    #[test]
+    fn rejects_missing_unit_and_bad_amount() {
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("xm").is_err());
+        assert!(parse_duration("5z").is_err());
+    }
+
+    #[test]
+    fn rejects_multi_byte_suffix_without_panicking() {
+        assert!(parse_duration("10°").is_err());
+    }
+}
@@ -62,7 +62,10 @@ macro_rules! make_errors {
 make_errors! {
     Io => std::io::Error,
     Toml => toml::de::Error,
-    Discord => serenity::Error
+    TomlSer => toml::ser::Error,
+    Discord => serenity::Error,
+    Sled => sled::Error,
+    Bincode => bincode::Error
 }
 
 impl fmt::Display for BotError {
@@ -82,7 +85,10 @@ impl fmt::Display for BotError {
                 }
             }
             BotError::Toml(e) => write!(f, "Error parsing configuration file! {}", e),
+            BotError::TomlSer(e) => write!(f, "Error serializing configuration file! {}", e),
             BotError::Discord(e) => write!(f, "Error communicating with Discord API! {}", e),
+            BotError::Sled(e) => write!(f, "Error accessing local storage! {}", e),
+            BotError::Bincode(e) => write!(f, "Error (de)serializing stored data! {}", e),
         }
     }
 }
@@ -1,9 +1,19 @@
+mod chunking;
+mod commands;
+mod expiry;
 mod logging;
+mod storage;
+mod webhook;
 use logging::*;
+use storage::StorageType;
+use webhook::WebhookMapType;
 
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+
+use serde::{Deserialize, Serialize};
 
 use serenity::http::Http;
+use serenity::model::interactions::Interaction;
 use serenity::model::user::User;
 use serenity::model::*;
 use serenity::model::{
@@ -19,29 +29,42 @@ type ChannelMap = std::collections::HashMap<id::ChannelId, Vec<TaggedMessage>>;
 
 const CONFIG_FILE_LOCATION: &str = "bot-config.toml";
 
-#[derive(Deserialize, Clone)]
+fn default_use_embeds() -> bool {
+    true
+}
+
+#[derive(Deserialize, Serialize, Clone)]
 struct Tag {
     channel_target: id::ChannelId,
     emoji_name: String,
     message_counter: u16,
+    #[serde(default = "default_use_embeds")]
+    use_embeds: bool,
+    /// Human-friendly duration (e.g. `"30m"`, `"2h"`) after which a tagged message is deleted,
+    /// regardless of channel activity. Falls back to the `message_counter` path when absent.
+    #[serde(default)]
+    expire_after: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct Config {
     token: String,
     roles: RolePermissions,
     tags: Vec<Tag>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct RolePermissions {
     console: u64,
     tag: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 struct TaggedMessage {
     message_id: id::MessageId,
     counter: u16,
+    /// Absolute deletion deadline, set when the originating tag has an `expire_after`.
+    deadline: Option<DateTime<Utc>>,
 }
 
 /// For use in serenity's Context::data to save state across handler calls.
@@ -59,25 +82,24 @@ impl TypeMapKey for ChannelMapType {
 struct Handler;
 impl EventHandler for Handler {
     fn message(&self, ctx: Context, msg: Message) {
-        if msg.content == "!tag help" {
-            const HELP_MSG: &str = "How to tag a message: \n \n 1. React with the appropriate emoji. \n 2. Wait for me to move it \n 3. ??? \n 4. Profit!";
-            match msg.channel_id.say(&ctx.http, HELP_MSG) {
-                Ok(_) => (),
-                Err(e) => BotError::Discord(e).log(),
-            }
+        let (db, mut tagged_messages) = {
+            let mut data = ctx.data.write();
+            let db = data.get::<StorageType>().unwrap().clone();
+            let channel_map = data.get_mut::<ChannelMapType>().unwrap();
+            let tagged_messages = channel_map.entry(msg.channel_id).or_insert_with(Vec::new).clone();
+            (db, tagged_messages)
         };
 
-        let mut data = ctx.data.write();
-        let channel_map = data.get_mut::<ChannelMapType>().unwrap();
-        let tagged_messages = match channel_map.get_mut(&msg.channel_id) {
-            Some(vec) => vec,
-            None => {
-                channel_map.insert(msg.channel_id, Vec::<TaggedMessage>::new());
-                channel_map.get_mut(&msg.channel_id).unwrap()
+        let mut mutated = false;
+
+        for message in tagged_messages.iter_mut() {
+            // Messages with a deadline are left to the background expiry task instead.
+            if message.deadline.is_some() {
+                continue;
             }
-        };
 
-        for message in tagged_messages {
+            mutated = true;
+
             if message.counter == 0 {
                 match ctx
                     .http
@@ -90,6 +112,16 @@ impl EventHandler for Handler {
                 message.counter -= 1;
             }
         }
+
+        if mutated {
+            {
+                let mut data = ctx.data.write();
+                let channel_map = data.get_mut::<ChannelMapType>().unwrap();
+                channel_map.insert(msg.channel_id, tagged_messages.clone());
+            }
+
+            storage::save_channel(&db, msg.channel_id, &tagged_messages).unwrap_gracefully();
+        }
     }
 
     fn reaction_add(&self, ctx: Context, reaction: Reaction) {
@@ -99,33 +131,75 @@ impl EventHandler for Handler {
                 id: _,
                 name,
             } => {
-                let mut data = ctx.data.write();
-                let tags = data.get_mut::<ConfigType>().unwrap().tags.to_owned();
-                let role = data.get_mut::<ConfigType>().unwrap().roles.tag.to_owned();
+                let (tags, role, db) = {
+                    let mut data = ctx.data.write();
+                    let tags = data.get_mut::<ConfigType>().unwrap().tags.to_owned();
+                    let role = data.get_mut::<ConfigType>().unwrap().roles.tag.to_owned();
+                    let db = data.get::<StorageType>().unwrap().clone();
+                    (tags, role, db)
+                };
 
-                let message = reaction.message(&ctx.http).unwrap_gracefully();
-                let channel_map = data.get_mut::<ChannelMapType>().unwrap();
-                let tagged_messages = match channel_map.get_mut(&reaction.channel_id) {
-                    Some(vec) => vec,
-                    None => {
-                        channel_map.insert(reaction.channel_id, Vec::<TaggedMessage>::new());
-                        channel_map.get_mut(&reaction.channel_id).unwrap()
+                // Resolve a webhook for every matching non-embed tag. The cache is only locked
+                // long enough to check/record it; the lookup-or-create HTTP call itself (on a
+                // cache miss) runs with the lock released.
+                let mut webhooks = std::collections::HashMap::new();
+                for tag in &tags {
+                    if name.as_ref().map(|n| n == &tag.emoji_name).unwrap_or(false) && !tag.use_embeds {
+                        let cached = {
+                            let mut data = ctx.data.write();
+                            data.get_mut::<WebhookMapType>().unwrap().get(&tag.channel_target).cloned()
+                        };
+
+                        let target_webhook = match cached {
+                            Some(webhook) => webhook,
+                            None => {
+                                let webhook = webhook::fetch_or_create_webhook(&ctx.http, tag.channel_target)
+                                    .unwrap_gracefully();
+                                let mut data = ctx.data.write();
+                                data.get_mut::<WebhookMapType>()
+                                    .unwrap()
+                                    .entry(tag.channel_target)
+                                    .or_insert(webhook)
+                                    .clone()
+                            }
+                        };
+
+                        webhooks.insert(tag.channel_target, target_webhook);
                     }
-                };
+                }
+
+                let message = reaction.message(&ctx.http).unwrap_gracefully();
                 let tagging_user = &reaction.user(&ctx.http).unwrap_gracefully();
+
                 match tagging_user.has_role(&ctx.http, reaction.guild_id.unwrap(), role) {
                     Ok(has_perms) => {
                         format!("{} {}", has_perms, role).log();
                         if has_perms {
+                            let mut tagged_messages = {
+                                let mut data = ctx.data.write();
+                                let channel_map = data.get_mut::<ChannelMapType>().unwrap();
+                                channel_map.entry(reaction.channel_id).or_insert_with(Vec::new).clone()
+                            };
+
                             tag_message(
                                 &ctx.http,
                                 &message,
                                 &tags,
-                                tagged_messages,
+                                &mut tagged_messages,
+                                &webhooks,
                                 name.as_ref().unwrap(),
                                 tagging_user,
                             )
                             .unwrap_gracefully();
+
+                            {
+                                let mut data = ctx.data.write();
+                                let channel_map = data.get_mut::<ChannelMapType>().unwrap();
+                                channel_map.insert(reaction.channel_id, tagged_messages.clone());
+                            }
+
+                            storage::save_channel(&db, reaction.channel_id, &tagged_messages)
+                                .unwrap_gracefully();
                         }
                     }
                     Err(e) => BotError::Discord(e).log(),
@@ -136,8 +210,16 @@ impl EventHandler for Handler {
         }
     }
 
-    fn ready(&self, _: Context, ready: Ready) {
+    fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let mut data = ctx.data.write();
+        let config = data.get_mut::<ConfigType>().unwrap();
+
+        commands::handle_interaction(&ctx.http, &interaction, config).unwrap_gracefully();
+    }
+
+    fn ready(&self, ctx: Context, ready: Ready) {
         format!("Connected to server as {}", ready.user.name).log();
+        commands::register_commands(&ctx.http).unwrap_gracefully();
     }
 }
 
@@ -147,6 +229,7 @@ fn tag_message(
     message: &Message,
     tags: &Vec<Tag>,
     tagged_messages: &mut Vec<TaggedMessage>,
+    webhooks: &std::collections::HashMap<id::ChannelId, serenity::model::webhook::Webhook>,
     name: &String,
     tagging_user: &User,
 ) -> Result<()> {
@@ -165,23 +248,52 @@ fn tag_message(
                 format!("User {} tagged post {}", tagging_user.tag(), message.id).log();
 
                 // Cite original message in target channel
-                let mut new_message = format!(
-                    "{} says (tagged by {})\n> {}",
-                    original_user.mention(),
-                    tagging_user.mention(),
-                    message.content
-                );
-                for attachment in &message.attachments {
-                    new_message.push_str("\n");
-                    new_message.push_str(&attachment.url)
-                }
+                if tag.use_embeds {
+                    tag.channel_target.send_message(http, |m| {
+                        m.embed(|e| {
+                            e.author(|a| a.name(original_user.tag()).icon_url(original_user.face()));
+                            e.description(&message.content);
+                            e.footer(|f| f.text(format!("Tagged by {}", tagging_user.tag())));
+                            e.timestamp(message.timestamp.to_rfc3339());
 
-                tag.channel_target.say(http, new_message)?;
+                            let mut image_set = false;
+                            for attachment in &message.attachments {
+                                if !image_set && attachment.width.is_some() {
+                                    e.image(&attachment.url);
+                                    image_set = true;
+                                } else {
+                                    e.field(&attachment.filename, &attachment.url, false);
+                                }
+                            }
+
+                            e
+                        })
+                    })?;
+                } else {
+                    let mut content = message.content.clone();
+                    for attachment in &message.attachments {
+                        content.push_str("\n");
+                        content.push_str(&attachment.url)
+                    }
+
+                    let webhook = webhooks.get(&tag.channel_target).expect(
+                        "webhook for this tag's target channel is resolved before tag_message is called",
+                    );
+                    webhook::repost_as_author(
+                        http,
+                        webhook,
+                        &content,
+                        &original_user.name,
+                        &original_user.face(),
+                        &format!("tagged by {}", tagging_user.tag()),
+                    )?;
+                }
 
                 // Add original message in tagged list
                 let entry = TaggedMessage {
                     message_id: message.id,
                     counter: tag.message_counter,
+                    deadline: expiry::deadline_for(&tag.expire_after),
                 };
 
                 tagged_messages.push(entry);
@@ -204,6 +316,10 @@ fn main() {
     format!("Loading configuration file: {}.", CONFIG_FILE_LOCATION).log();
     let config = read_parse_config().unwrap_gracefully();
 
+    format!("Opening local storage.").log();
+    let db = storage::open().unwrap_gracefully();
+    let channel_map = storage::load_channel_map(&db).unwrap_gracefully();
+
     let mut client = match Client::new(&config.token, Handler) {
         Ok(c) => c,
         Err(e) => {
@@ -213,9 +329,15 @@ fn main() {
     };
 
     format!("Connecting to Discord API.").log();
-    client.data.write().insert::<ChannelMapType>(ChannelMap::new());
+    let expiry_http = Http::new_with_token(&config.token);
+    client.data.write().insert::<ChannelMapType>(channel_map);
+    client.data.write().insert::<WebhookMapType>(std::collections::HashMap::new());
+    client.data.write().insert::<StorageType>(db);
     client.data.write().insert::<ConfigType>(config);
 
+    format!("Starting tagged message expiry task.").log();
+    expiry::spawn_expiry_task(expiry_http, client.data.clone());
+
     if let Err(e) = client.start() {
         BotError::Discord(e).log();
     }
@@ -0,0 +1,44 @@
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+
+use crate::logging::*;
+use crate::{ChannelMap, TaggedMessage};
+
+const STORAGE_DB_LOCATION: &str = "tag-bot-db";
+
+/// For use in serenity's Context::data to save state across handler calls.
+pub struct StorageType;
+impl TypeMapKey for StorageType {
+    type Value = sled::Db;
+}
+
+/// Opens (creating if necessary) the embedded key-value store backing `ChannelMap` persistence.
+pub fn open() -> Result<sled::Db> {
+    Ok(sled::open(STORAGE_DB_LOCATION)?)
+}
+
+/// Loads every persisted channel's tagged messages back into a `ChannelMap`, so scheduled
+/// deletions survive a restart.
+pub fn load_channel_map(db: &sled::Db) -> Result<ChannelMap> {
+    let mut channel_map = ChannelMap::new();
+
+    for entry in db.iter() {
+        let (key, value) = entry?;
+        let channel = ChannelId(u64::from_be_bytes(key.as_ref().try_into().unwrap()));
+        let tagged_messages: Vec<TaggedMessage> = bincode::deserialize(&value)?;
+        channel_map.insert(channel, tagged_messages);
+    }
+
+    Ok(channel_map)
+}
+
+/// Persists the tagged messages for a single channel, overwriting whatever was stored for it.
+pub fn save_channel(db: &sled::Db, channel: ChannelId, tagged_messages: &[TaggedMessage]) -> Result<()> {
+    let key = u64::from(channel).to_be_bytes();
+    let value = bincode::serialize(tagged_messages)?;
+
+    db.insert(key, value)?;
+    db.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use serenity::model::webhook::Webhook;
+use serenity::prelude::*;
+
+use std::collections::HashMap;
+
+use crate::chunking::StrChunksExt;
+
+const WEBHOOK_NAME: &str = "tag-bot";
+
+/// Discord's hard limit on a single message's content length.
+const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+/// For use in serenity's Context::data to save state across handler calls.
+///
+/// Caches one webhook per target channel so `tag_message` doesn't have to hit the API
+/// to look it up (or create one) on every tag.
+pub struct WebhookMapType;
+impl TypeMapKey for WebhookMapType {
+    type Value = HashMap<ChannelId, Webhook>;
+}
+
+/// Looks up or creates the `tag-bot` webhook for `channel` over HTTP, without touching the
+/// cache. Callers should check `WebhookMapType` first and only reach for this on a cache miss,
+/// so the (possibly slow) network calls happen without holding the data lock.
+pub fn fetch_or_create_webhook(http: &Http, channel: ChannelId) -> crate::logging::Result<Webhook> {
+    let existing = http
+        .get_channel_webhooks(u64::from(channel))?
+        .into_iter()
+        .find(|w| w.name.as_deref() == Some(WEBHOOK_NAME));
+
+    match existing {
+        Some(webhook) => Ok(webhook),
+        None => Ok(channel.create_webhook(http, WEBHOOK_NAME)?),
+    }
+}
+
+/// Reposts `content` through `webhook`, impersonating the original author via `username`/
+/// `avatar_url`. Content over Discord's 2000-character limit is sent as several chunked
+/// messages. Returns the last message sent.
+pub fn repost_as_author(
+    http: &Http,
+    webhook: &Webhook,
+    content: &str,
+    username: &str,
+    avatar_url: &str,
+    footer: &str,
+) -> crate::logging::Result<Message> {
+    let full_content = format!("{}\n-# {}", content, footer);
+
+    let mut last_message = None;
+    for chunk in full_content.chunks(DISCORD_MESSAGE_LIMIT) {
+        last_message = webhook.execute(http, true, |w| {
+            w.content(chunk).username(username).avatar_url(avatar_url)
+        })?;
+    }
+
+    Ok(last_message.expect("execute with wait=true always returns a message"))
+}